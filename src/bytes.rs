@@ -1,10 +1,14 @@
 /// Bytes compatibility module
 ///
 /// This module provides a `Bytes` type that wraps `FigBuf<[u8]>` and provides
-/// an API similar to the popular `bytes` crate.
+/// an API similar to the popular `bytes` crate, along with a `BytesMut`
+/// builder that freezes into `Bytes` without copying.
+use crate::reclaim::ReclaimHandle;
 use crate::FigBuf;
 use std::fmt;
-use std::ops::{Deref, RangeBounds};
+use std::io;
+use std::ops::{Deref, DerefMut, RangeBounds};
+use std::panic::RefUnwindSafe;
 
 /// A reference-counted byte buffer compatible with the bytes crate API.
 ///
@@ -37,6 +41,48 @@ impl Bytes {
         }
     }
 
+    /// Creates a `Bytes` that points into memory owned by `owner`, without
+    /// copying it.
+    ///
+    /// The owner can be anything that exposes its bytes through `AsRef<[u8]>`,
+    /// such as a memory-mapped file or a buffer handed over from FFI. It is
+    /// kept alive until the last `Bytes` sliced from it is dropped.
+    ///
+    /// The owner must be `RefUnwindSafe`, so that `Bytes` stays unwind safe.
+    /// Owners with interior mutability can be wrapped in `AssertUnwindSafe`
+    /// after checking that a panic cannot leave them in a broken state.
+    ///
+    /// The owner's memory is never written to, so [`try_into_vec`](Self::try_into_vec)
+    /// always fails and mutation copies the bytes first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fig::bytes::Bytes;
+    ///
+    /// struct Mapping(Box<[u8]>);
+    ///
+    /// impl AsRef<[u8]> for Mapping {
+    ///     fn as_ref(&self) -> &[u8] {
+    ///         &self.0
+    ///     }
+    /// }
+    ///
+    /// let bytes = Bytes::from_owner(Mapping(Box::from(&b"header|body"[..])));
+    /// let body = bytes.slice(7..);
+    ///
+    /// assert_eq!(&*body, b"body");
+    /// assert_eq!(body.as_ptr(), bytes[7..].as_ptr());
+    /// ```
+    pub fn from_owner<O>(owner: O) -> Self
+    where
+        O: AsRef<[u8]> + Send + Sync + RefUnwindSafe + 'static,
+    {
+        Self {
+            inner: FigBuf::<[u8]>::from_owner(owner),
+        }
+    }
+
     /// Returns the number of bytes in the buffer.
     pub fn len(&self) -> usize {
         self.inner.len()
@@ -70,6 +116,93 @@ impl Bytes {
         }
     }
 
+    /// Converts back into a [`BytesMut`] without copying.
+    ///
+    /// This only succeeds if there is exactly one strong reference to the
+    /// data and no [`ReclaimHandle`] is still watching it; a handle stops
+    /// counting once it has reported the buffer as reclaimable. Otherwise, or
+    /// if the bytes are static or belong to an external owner, the original
+    /// `Bytes` is returned unchanged.
+    ///
+    /// The returned builder holds all the bytes of this `Bytes`, and its
+    /// capacity is initially its length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fig::bytes::Bytes;
+    ///
+    /// let bytes = Bytes::from_vec(b"reuse me".to_vec());
+    /// let ptr = bytes.as_ptr();
+    ///
+    /// let mut buf = bytes.try_into_mut().unwrap();
+    /// buf.clear();
+    /// buf.put_slice(b"again");
+    ///
+    /// assert_eq!(buf.as_ptr(), ptr);
+    /// ```
+    pub fn try_into_mut(self) -> Result<BytesMut, Self> {
+        match self.inner.into_writable() {
+            Ok(buf) => Ok(BytesMut {
+                len: buf.len(),
+                buf,
+            }),
+            Err(inner) => Err(Self { inner }),
+        }
+    }
+
+    /// Returns a handle that becomes ready once this buffer's allocation is
+    /// no longer shared with any other `Bytes`.
+    ///
+    /// Returns `None` if the buffer has no heap allocation to reclaim.
+    pub fn reclaim_handle(&self) -> Option<ReclaimHandle<[u8]>> {
+        self.inner.reclaim_handle()
+    }
+
+    /// Returns the length of the longest common prefix of `self` and `other`.
+    ///
+    /// Compares a machine word at a time.
+    pub fn common_prefix_len(&self, other: &[u8]) -> usize {
+        self.inner.common_prefix_len(other)
+    }
+
+    /// Splits off the longest common prefix of `self` and `other`.
+    ///
+    /// Returns `(prefix, self_rest, other_rest)`. All three share the
+    /// underlying data of their source buffers, with the prefix taken from
+    /// `self`.
+    pub fn split_common_prefix(&self, other: &Self) -> (Self, Self, Self) {
+        let (prefix, rest, other_rest) = self.inner.split_common_prefix(&other.inner);
+        (
+            Self { inner: prefix },
+            Self { inner: rest },
+            Self { inner: other_rest },
+        )
+    }
+
+    /// Returns `true` if the start of the buffer is aligned to `align` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn is_aligned(&self, align: usize) -> bool {
+        self.inner.is_aligned(align)
+    }
+
+    /// Ensures the start of the buffer is aligned to `align` bytes.
+    ///
+    /// If the buffer is already aligned this does nothing. Otherwise the bytes
+    /// are copied into a new allocation whose view starts at an aligned
+    /// address, so later slices at multiples of `align` stay aligned too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if the padded buffer would
+    /// not fit in memory.
+    pub fn ensure_aligned(&mut self, align: usize) {
+        self.inner.ensure_aligned(align);
+    }
+
     /// Returns a copy of the bytes as a `Vec<u8>`.
     pub fn to_vec(&self) -> Vec<u8> {
         self.inner.as_slice().to_vec()
@@ -106,6 +239,70 @@ impl Bytes {
     pub fn clear(&mut self) {
         self.inner = FigBuf::from_vec(Vec::new());
     }
+
+    /// Replaces all non-overlapping occurrences of `needle` with `replacement`.
+    ///
+    /// If `needle` does not occur, the returned `Bytes` shares the original
+    /// buffer without copying. Otherwise the result is built in a single
+    /// allocation of the exact output size.
+    ///
+    /// As with `str::replace`, an empty `needle` matches before every byte
+    /// and at the end of the buffer.
+    pub fn replace(&self, needle: &[u8], replacement: &[u8]) -> Self {
+        self.replacen(needle, replacement, usize::MAX)
+    }
+
+    /// Replaces the first `count` non-overlapping occurrences of `needle`
+    /// with `replacement`.
+    ///
+    /// Shares the original buffer when nothing is replaced, see [`Bytes::replace`].
+    pub fn replacen(&self, needle: &[u8], replacement: &[u8], count: usize) -> Self {
+        let haystack = self.as_slice();
+        let matches = MatchIndices::new(haystack, needle).take(count).count();
+        if matches == 0 {
+            return self.clone();
+        }
+
+        let out_len = haystack.len() - matches * needle.len() + matches * replacement.len();
+        let mut out = Vec::with_capacity(out_len);
+        let mut last = 0;
+        for start in MatchIndices::new(haystack, needle).take(matches) {
+            out.extend_from_slice(&haystack[last..start]);
+            out.extend_from_slice(replacement);
+            last = start + needle.len();
+        }
+        out.extend_from_slice(&haystack[last..]);
+
+        Self::from_vec(out)
+    }
+
+    /// Returns an iterator over `chunk_size` chunks of the buffer, starting at
+    /// the end.
+    ///
+    /// Each chunk shares the underlying data. The last chunk yielded (the
+    /// front of the buffer) may be shorter than `chunk_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn rchunks(
+        &self,
+        chunk_size: usize,
+    ) -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
+        self.inner.rchunks(chunk_size).map(|inner| Self { inner })
+    }
+
+    /// Returns an iterator over subslices separated by bytes matching `pred`,
+    /// starting at the end.
+    ///
+    /// The matched bytes are not included. Each subslice shares the
+    /// underlying data.
+    pub fn rsplit<F>(&self, pred: F) -> impl Iterator<Item = Self>
+    where
+        F: FnMut(&u8) -> bool,
+    {
+        self.inner.rsplit(pred).map(|inner| Self { inner })
+    }
 }
 
 impl Default for Bytes {
@@ -202,6 +399,453 @@ impl PartialEq<Bytes> for &[u8] {
     }
 }
 
+/// Lets `Bytes` be submitted directly to `tokio-uring` write operations.
+///
+/// The runtime takes ownership of the buffer for the duration of the
+/// operation; the bytes stay at a fixed address because they live in a
+/// shared heap allocation.
+///
+/// Note that with `IoBuf` in scope, `bytes.slice(range)` resolves to
+/// `IoBuf::slice`, which consumes the buffer. Call `Bytes::slice(&bytes, range)`
+/// for a zero-copy subslice instead.
+// SAFETY: see the `IoBuf` impl for `FigBuf<[u8]>`, which this delegates to.
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+unsafe impl tokio_uring::buf::IoBuf for Bytes {
+    fn stable_ptr(&self) -> *const u8 {
+        self.inner.stable_ptr()
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.inner.bytes_init()
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.inner.bytes_total()
+    }
+}
+
+/// A growable byte buffer that freezes into [`Bytes`] without copying.
+///
+/// `BytesMut` writes directly into a shared allocation. Splitting it with
+/// [`split_to`](Self::split_to) or [`split_off`](Self::split_off) hands out
+/// disjoint regions of the same allocation, and [`freeze`](Self::freeze)
+/// turns a region into an immutable `Bytes` that shares it. This lets an
+/// encoder write frame after frame into one allocation and send each one off
+/// as `Bytes` while continuing to write.
+///
+/// Once every frozen `Bytes` from an allocation has been dropped, `reserve`
+/// reuses the space they occupied instead of allocating again.
+///
+/// # Example
+///
+/// ```
+/// use fig::bytes::BytesMut;
+/// use std::io::Write;
+///
+/// let mut buf = BytesMut::with_capacity(64);
+///
+/// buf.put_slice(b"HELLO ");
+/// write!(buf, "{}", 42).unwrap();
+/// let frame = buf.split().freeze();
+///
+/// buf.put_slice(b"next frame");
+///
+/// assert_eq!(&*frame, b"HELLO 42");
+/// assert_eq!(&*buf, b"next frame");
+/// ```
+pub struct BytesMut {
+    // View over the region of the allocation owned by this builder. Nothing
+    // else reads or writes this region while the builder is alive.
+    buf: FigBuf<[u8]>,
+    len: usize,
+}
+
+impl BytesMut {
+    /// Creates a new, empty `BytesMut`.
+    ///
+    /// This does not allocate until bytes are written.
+    pub fn new() -> Self {
+        Self {
+            buf: FigBuf::<[u8]>::from_static(&[]),
+            len: 0,
+        }
+    }
+
+    /// Creates a new `BytesMut` that can hold at least `capacity` bytes
+    /// without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: FigBuf::writable(capacity),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of bytes written to the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no bytes have been written to the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Reserves space for at least `additional` more bytes.
+    ///
+    /// If the allocation is no longer shared with any split-off or frozen
+    /// buffer, the existing bytes are moved to its start and its full size is
+    /// reused. Otherwise the bytes are copied into a new allocation.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.capacity() - self.len >= additional {
+            return;
+        }
+
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if self.buf.reclaim_allocation(self.len, needed) {
+            return;
+        }
+
+        let capacity = std::cmp::max(needed, self.capacity() * 2);
+        let mut buf = FigBuf::writable(capacity);
+        buf.get_mut().expect("freshly allocated")[..self.len].copy_from_slice(self);
+        self.buf = buf;
+    }
+
+    /// Appends a byte slice to the buffer, growing it if needed.
+    pub fn put_slice(&mut self, src: &[u8]) {
+        self.reserve(src.len());
+        // SAFETY: `reserve` guarantees room for `src.len()` bytes after `len`
+        // inside the region this builder owns.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                src.as_ptr(),
+                self.buf.as_mut_ptr().add(self.len),
+                src.len(),
+            );
+        }
+        self.len += src.len();
+    }
+
+    /// Appends a byte slice to the buffer, growing it if needed.
+    ///
+    /// This is an alias for [`put_slice`](Self::put_slice).
+    pub fn extend_from_slice(&mut self, src: &[u8]) {
+        self.put_slice(src);
+    }
+
+    /// Appends a single byte to the buffer.
+    pub fn put_u8(&mut self, byte: u8) {
+        self.put_slice(&[byte]);
+    }
+
+    /// Splits the buffer into two at the given index.
+    ///
+    /// Afterwards `self` contains elements `[0, at)` with capacity `at`, and
+    /// the returned `BytesMut` contains elements `[at, len)` along with the
+    /// rest of the capacity. Both keep writing into the same allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > capacity`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.capacity(), "split_off out of bounds");
+        let other = Self {
+            buf: self.buf.slice(at..),
+            len: self.len.saturating_sub(at),
+        };
+        self.buf = self.buf.slice(..at);
+        self.len = std::cmp::min(self.len, at);
+        other
+    }
+
+    /// Splits the buffer into two at the given index.
+    ///
+    /// Afterwards `self` contains elements `[at, len)` along with the rest of
+    /// the capacity, and the returned `BytesMut` contains elements `[0, at)`.
+    /// Both keep writing into the same allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_to(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split_to out of bounds");
+        let head = Self {
+            buf: self.buf.slice(..at),
+            len: at,
+        };
+        self.buf = self.buf.slice(at..);
+        self.len -= at;
+        head
+    }
+
+    /// Removes all written bytes, returning them as a new `BytesMut`.
+    ///
+    /// `self` keeps the remaining capacity. This is equivalent to
+    /// `split_to(len())`.
+    pub fn split(&mut self) -> Self {
+        self.split_to(self.len)
+    }
+
+    /// Converts the written bytes into an immutable `Bytes` without copying.
+    ///
+    /// The returned `Bytes` shares the allocation. Unused capacity is
+    /// released back to the allocation rather than handed to the `Bytes`.
+    pub fn freeze(self) -> Bytes {
+        Bytes {
+            inner: self.buf.slice(..self.len),
+        }
+    }
+
+    /// Shortens the buffer to `len` bytes, keeping the capacity.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = std::cmp::min(self.len, len);
+    }
+
+    /// Removes all written bytes, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns an adapter that appends formatted text to the buffer.
+    ///
+    /// The adapter implements `fmt::Write`, so `write!` writes UTF-8 text
+    /// straight into the buffer without building an intermediate `String`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fig::bytes::BytesMut;
+    /// use std::fmt::Write;
+    ///
+    /// let mut buf = BytesMut::with_capacity(64);
+    /// write!(buf.as_fmt_writer(), "HTTP/1.1 {} {}\r\n", 200, "OK").unwrap();
+    ///
+    /// assert_eq!(&*buf, b"HTTP/1.1 200 OK\r\n");
+    /// ```
+    pub fn as_fmt_writer(&mut self) -> FmtWriter<'_> {
+        FmtWriter { buf: self }
+    }
+}
+
+/// A `fmt::Write` adapter that appends to a [`BytesMut`].
+///
+/// Created by [`BytesMut::as_fmt_writer`].
+pub struct FmtWriter<'a> {
+    buf: &'a mut BytesMut,
+}
+
+impl fmt::Write for FmtWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.put_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FmtWriter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FmtWriter").field(&self.buf).finish()
+    }
+}
+
+impl Default for BytesMut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for BytesMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf.as_slice()[..self.len]
+    }
+}
+
+impl DerefMut for BytesMut {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the first `len` bytes of the region are initialized and
+        // owned exclusively by this builder, which is borrowed mutably.
+        unsafe { std::slice::from_raw_parts_mut(self.buf.as_mut_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for BytesMut {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for BytesMut {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl Clone for BytesMut {
+    fn clone(&self) -> Self {
+        Self::from(&self[..])
+    }
+}
+
+impl io::Write for BytesMut {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.put_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.put_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Extend<u8> for BytesMut {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for byte in iter {
+            self.put_u8(byte);
+        }
+    }
+}
+
+impl From<&[u8]> for BytesMut {
+    fn from(src: &[u8]) -> Self {
+        let mut buf = Self::with_capacity(src.len());
+        buf.put_slice(src);
+        buf
+    }
+}
+
+impl From<&str> for BytesMut {
+    fn from(s: &str) -> Self {
+        Self::from(s.as_bytes())
+    }
+}
+
+impl From<BytesMut> for Bytes {
+    fn from(buf: BytesMut) -> Self {
+        buf.freeze()
+    }
+}
+
+impl fmt::Debug for BytesMut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self[..], f)
+    }
+}
+
+impl PartialEq for BytesMut {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl Eq for BytesMut {}
+
+impl PartialEq<[u8]> for BytesMut {
+    fn eq(&self, other: &[u8]) -> bool {
+        &self[..] == other
+    }
+}
+
+impl PartialEq<&[u8]> for BytesMut {
+    fn eq(&self, other: &&[u8]) -> bool {
+        &self[..] == *other
+    }
+}
+
+/// Lets `BytesMut` be submitted to `tokio-uring` write operations.
+///
+/// Only the written bytes are submitted.
+// SAFETY: the bytes live in a heap allocation that does not move with the
+// `BytesMut`, and no one else writes to this builder's region.
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+unsafe impl tokio_uring::buf::IoBuf for BytesMut {
+    fn stable_ptr(&self) -> *const u8 {
+        self.buf.as_mut_ptr()
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.len
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.capacity()
+    }
+}
+
+/// Lets `BytesMut` be filled by `tokio-uring` read operations.
+///
+/// As with `Vec<u8>`, a read fills the buffer from its start, up to its
+/// capacity, and extends the length to cover the bytes read. To append after
+/// the written bytes, submit `IoBuf::slice(buf, buf.len()..)` instead. Call
+/// [`reserve`](BytesMut::reserve) first to make room.
+// SAFETY: the whole capacity is initialized (zeroed on allocation) and owned
+// exclusively by this builder, so the kernel may write anywhere in it, and any
+// `pos` up to the capacity is a valid length.
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+unsafe impl tokio_uring::buf::IoBufMut for BytesMut {
+    fn stable_mut_ptr(&mut self) -> *mut u8 {
+        self.buf.as_mut_ptr()
+    }
+
+    unsafe fn set_init(&mut self, pos: usize) {
+        if self.len < pos {
+            self.len = pos;
+        }
+    }
+}
+
+/// Iterator over the start positions of non-overlapping `needle` matches.
+struct MatchIndices<'a> {
+    haystack: &'a [u8],
+    needle: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> MatchIndices<'a> {
+    fn new(haystack: &'a [u8], needle: &'a [u8]) -> Self {
+        Self {
+            haystack,
+            needle,
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for MatchIndices<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+
+        if self.needle.is_empty() {
+            let start = self.pos;
+            self.pos += 1;
+            return Some(start);
+        }
+
+        let offset = self.haystack[self.pos..]
+            .windows(self.needle.len())
+            .position(|window| window == self.needle)?;
+        let start = self.pos + offset;
+        self.pos = start + self.needle.len();
+        Some(start)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +905,82 @@ mod tests {
         assert_eq!(&*bytes, b"hello");
     }
 
+    #[test]
+    fn test_bytes_replace() {
+        let bytes = Bytes::from_static(b"a-b-c");
+        assert_eq!(&*bytes.replace(b"-", b"::"), b"a::b::c");
+        assert_eq!(&*bytes.replacen(b"-", b"", 1), b"ab-c");
+    }
+
+    #[test]
+    fn test_bytes_mut_freeze() {
+        let mut buf = BytesMut::with_capacity(16);
+        buf.put_slice(b"hello");
+        let ptr = buf.as_ptr();
+
+        let frozen = buf.freeze();
+        assert_eq!(&*frozen, b"hello");
+        assert_eq!(frozen.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_bytes_mut_fmt_writer() {
+        use std::fmt::Write;
+
+        let mut buf = BytesMut::new();
+        let (code, reason) = (404, "Not Found");
+        write!(buf.as_fmt_writer(), "HTTP/1.1 {} {}\r\n", code, reason).unwrap();
+        buf.as_fmt_writer().write_char('\u{e9}').unwrap();
+
+        assert_eq!(&*buf, "HTTP/1.1 404 Not Found\r\n\u{e9}".as_bytes());
+    }
+
+    #[test]
+    fn test_bytes_try_into_mut_static() {
+        static DATA: [u8; 4] = *b"data";
+        let bytes = Bytes {
+            inner: FigBuf::<[u8]>::from_static(&DATA),
+        };
+
+        let bytes = bytes.try_into_mut().unwrap_err();
+        assert_eq!(bytes.as_ptr(), DATA.as_ptr());
+    }
+
+    #[test]
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    fn test_bytes_mut_io_buf_mut() {
+        use tokio_uring::buf::{IoBuf, IoBufMut};
+
+        let mut buf = BytesMut::with_capacity(8);
+        buf.put_slice(b"ab");
+
+        assert_eq!(buf.bytes_init(), 2);
+        assert_eq!(buf.bytes_total(), 8);
+        assert_eq!(buf.stable_ptr(), buf.as_ptr());
+
+        // Simulate the kernel filling the spare capacity.
+        unsafe {
+            std::ptr::copy_nonoverlapping(b"cde".as_ptr(), buf.stable_mut_ptr().add(2), 3);
+            buf.set_init(5);
+            buf.set_init(1);
+        }
+        assert_eq!(&*buf, b"abcde");
+    }
+
+    #[test]
+    fn test_bytes_mut_reserve_reclaims() {
+        let mut buf = BytesMut::with_capacity(8);
+        buf.put_slice(b"abcdef");
+        let start = buf.as_ptr();
+
+        drop(buf.split_to(4));
+        buf.reserve(6);
+
+        assert_eq!(&*buf, b"ef");
+        assert_eq!(buf.as_ptr(), start);
+        assert_eq!(buf.capacity(), 8);
+    }
+
     #[test]
     fn test_bytes_empty() {
         let bytes = Bytes::new();
@@ -2,16 +2,103 @@ use std::borrow::Borrow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::io::{self, Read, Write};
+use std::mem::ManuallyDrop;
 use std::ops::{Deref, RangeBounds};
+use std::panic::RefUnwindSafe;
+use std::ptr::NonNull;
 use std::sync::Arc;
 
 pub mod bytes;
+pub mod reclaim;
 pub mod small;
+#[cfg(feature = "testing")]
+pub mod testing;
 
+use reclaim::ReclaimHandle;
+
+#[inline]
+fn record_alloc() {
+    #[cfg(feature = "testing")]
+    testing::record_alloc();
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    const WORD: usize = std::mem::size_of::<usize>();
+
+    let len = std::cmp::min(a.len(), b.len());
+    let mut i = 0;
+    while i + WORD <= len {
+        let x = usize::from_le_bytes(a[i..i + WORD].try_into().unwrap());
+        let y = usize::from_le_bytes(b[i..i + WORD].try_into().unwrap());
+        let diff = x ^ y;
+        if diff != 0 {
+            return i + diff.trailing_zeros() as usize / 8;
+        }
+        i += WORD;
+    }
+
+    while i < len && a[i] == b[i] {
+        i += 1;
+    }
+    i
+}
+
+// An external value that owns the buffer's memory, such as a memory map.
+pub(crate) type Owner<T> = dyn AsRef<T> + Send + Sync + RefUnwindSafe;
+
+// Storage that a `BytesMut` keeps writing into while buffers split off or
+// frozen from it read other parts. It is shared as `Arc<Writable<T>>`, so the
+// reference count lives in a header allocation of its own: cloning, counting
+// and dropping buffers only ever borrow the header, never the bytes being
+// written. The data is a uniquely owned `Arc<T>`, so a heap buffer becomes
+// writable without copying, and it is only reached through raw pointers to a
+// buffer's own range or through `get_mut` once the header is unique.
+pub(crate) struct Writable<T: ?Sized + 'static> {
+    data: NonNull<T>,
+}
+
+// SAFETY: this owns the data. A range is only written by the `BytesMut` that
+// owns it, which hands it to readers by being moved.
+unsafe impl<T: ?Sized + Send + Sync> Send for Writable<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for Writable<T> {}
+
+impl<T: ?Sized + 'static> Writable<T> {
+    fn from_arc(arc: Arc<T>) -> Arc<Self> {
+        debug_assert_eq!(Arc::strong_count(&arc), 1);
+        // SAFETY: `Arc::into_raw` never returns null.
+        let data = unsafe { NonNull::new_unchecked(Arc::into_raw(arc) as *mut T) };
+        Arc::new(Self { data })
+    }
+
+    fn as_ptr(&self) -> *mut T {
+        self.data.as_ptr()
+    }
+
+    // A mutable borrow of the header means no other buffer shares the data.
+    fn get_mut(&mut self) -> &mut T {
+        // SAFETY: the data is owned by this header alone.
+        unsafe { &mut *self.data.as_ptr() }
+    }
+}
+
+impl<T: ?Sized + 'static> Drop for Writable<T> {
+    fn drop(&mut self) {
+        // SAFETY: releases the reference taken over in `from_arc`. The last
+        // header reference is being dropped, so every write happened before.
+        drop(unsafe { Arc::from_raw(self.data.as_ptr()) });
+    }
+}
+
+// Only `Writable` data is written while shared, so it is the only variant
+// whose data is never borrowed as a whole while shared.
 enum Inner<T: ?Sized + 'static> {
     Static(&'static T),
 
     Arc(Arc<T>),
+
+    Owner(Arc<Owner<T>>),
+
+    Writable(Arc<Writable<T>>),
 }
 
 impl<T: ?Sized + 'static> Clone for Inner<T> {
@@ -19,21 +106,42 @@ impl<T: ?Sized + 'static> Clone for Inner<T> {
         match self {
             Inner::Static(s) => Inner::Static(s),
             Inner::Arc(arc) => Inner::Arc(Arc::clone(arc)),
+            Inner::Owner(owner) => Inner::Owner(Arc::clone(owner)),
+            Inner::Writable(arc) => Inner::Writable(Arc::clone(arc)),
         }
     }
 }
 
 pub struct FigBuf<T: ?Sized + 'static> {
-    inner: Inner<T>,
+    inner: ManuallyDrop<Inner<T>>,
     offset: usize,
     len: usize,
 }
 
+impl<T: ?Sized + 'static> Drop for FigBuf<T> {
+    fn drop(&mut self) {
+        let addr = match &*self.inner {
+            Inner::Static(_) => None,
+            Inner::Arc(arc) => Some(Arc::as_ptr(arc) as *const () as usize),
+            Inner::Owner(owner) => Some(Arc::as_ptr(owner) as *const () as usize),
+            Inner::Writable(arc) => Some(Arc::as_ptr(arc) as *const () as usize),
+        };
+
+        // SAFETY: `inner` is never touched again after this point.
+        unsafe { ManuallyDrop::drop(&mut self.inner) };
+
+        if let Some(addr) = addr {
+            reclaim::notify(addr);
+        }
+    }
+}
+
 impl<T: 'static> FigBuf<[T]> {
     pub fn from_vec(vec: Vec<T>) -> Self {
         let len = vec.len();
+        record_alloc();
         Self {
-            inner: Inner::Arc(Arc::from(vec.into_boxed_slice())),
+            inner: ManuallyDrop::new(Inner::Arc(Arc::from(vec.into_boxed_slice()))),
             offset: 0,
             len,
         }
@@ -41,8 +149,9 @@ impl<T: 'static> FigBuf<[T]> {
 
     pub fn from_boxed_slice(slice: Box<[T]>) -> Self {
         let len = slice.len();
+        record_alloc();
         Self {
-            inner: Inner::Arc(Arc::from(slice)),
+            inner: ManuallyDrop::new(Inner::Arc(Arc::from(slice))),
             offset: 0,
             len,
         }
@@ -50,12 +159,26 @@ impl<T: 'static> FigBuf<[T]> {
 
     pub fn from_static(slice: &'static [T]) -> Self {
         Self {
-            inner: Inner::Static(slice),
+            inner: ManuallyDrop::new(Inner::Static(slice)),
             offset: 0,
             len: slice.len(),
         }
     }
 
+    // Wraps memory owned by `owner` without copying it. The owner is kept
+    // alive until the last buffer referring to it is dropped.
+    pub fn from_owner<O>(owner: O) -> Self
+    where
+        O: AsRef<[T]> + Send + Sync + RefUnwindSafe + 'static,
+    {
+        let len = owner.as_ref().len();
+        Self {
+            inner: ManuallyDrop::new(Inner::Owner(Arc::new(owner))),
+            offset: 0,
+            len,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -90,19 +213,30 @@ impl<T: 'static> FigBuf<[T]> {
     }
 
     pub fn as_slice(&self) -> &[T] {
-        let full_slice = match &self.inner {
-            Inner::Static(s) => s,
+        let full_slice = match &*self.inner {
+            Inner::Static(s) => *s,
             Inner::Arc(arc) => &**arc,
+            Inner::Owner(owner) => (**owner).as_ref(),
+            // SAFETY: the view is in bounds, and nothing writes to it while
+            // this buffer can read it.
+            Inner::Writable(arc) => unsafe {
+                return std::slice::from_raw_parts(
+                    (arc.as_ptr() as *const T).add(self.offset),
+                    self.len,
+                );
+            },
         };
         &full_slice[self.offset..self.offset + self.len]
     }
 
     pub fn get_mut(&mut self) -> Option<&mut [T]> {
-        match &mut self.inner {
-            Inner::Static(_) => None,
+        match &mut *self.inner {
+            Inner::Static(_) | Inner::Owner(_) => None,
             Inner::Arc(arc) => {
                 Arc::get_mut(arc).map(|slice| &mut slice[self.offset..self.offset + self.len])
             }
+            Inner::Writable(arc) => Arc::get_mut(arc)
+                .map(|data| &mut data.get_mut()[self.offset..self.offset + self.len]),
         }
     }
 
@@ -114,11 +248,15 @@ impl<T: 'static> FigBuf<[T]> {
     where
         T: Clone,
     {
-        let needs_clone = match &self.inner {
-            Inner::Static(_) => true,
+        let needs_clone = match &mut *self.inner {
+            Inner::Static(_) | Inner::Owner(_) => true,
             Inner::Arc(arc) => {
-                self.offset != 0 || self.len != arc.len() || Arc::strong_count(arc) > 1
+                self.offset != 0 || self.len != arc.len() || Arc::get_mut(arc).is_none()
             }
+            Inner::Writable(arc) => match Arc::get_mut(arc) {
+                Some(data) => self.offset != 0 || self.len != data.get_mut().len(),
+                None => true,
+            },
         };
 
         if needs_clone {
@@ -130,40 +268,188 @@ impl<T: 'static> FigBuf<[T]> {
     }
 
     pub fn ref_count(&self) -> usize {
-        match &self.inner {
+        match &*self.inner {
             Inner::Static(_) => usize::MAX,
             Inner::Arc(arc) => Arc::strong_count(arc),
+            Inner::Owner(owner) => Arc::strong_count(owner),
+            Inner::Writable(arc) => Arc::strong_count(arc),
         }
     }
 
     pub fn is_static(&self) -> bool {
-        matches!(&self.inner, Inner::Static(_))
+        matches!(&*self.inner, Inner::Static(_))
+    }
+
+    pub fn reclaim_handle(&self) -> Option<ReclaimHandle<[T]>> {
+        match &*self.inner {
+            Inner::Static(_) => None,
+            Inner::Arc(arc) => Some(ReclaimHandle::new(Arc::downgrade(arc))),
+            Inner::Owner(owner) => Some(ReclaimHandle::from_owner(Arc::downgrade(owner))),
+            Inner::Writable(arc) => Some(ReclaimHandle::from_writable(Arc::downgrade(arc))),
+        }
+    }
+
+    pub fn rchunks(&self, chunk_size: usize) -> RChunks<T> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        RChunks {
+            buf: self.clone(),
+            chunk_size,
+        }
+    }
+
+    pub fn rsplit<F>(&self, pred: F) -> RSplit<T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        RSplit {
+            buf: self.clone(),
+            pred,
+            finished: false,
+        }
     }
 }
 
-impl FigBuf<str> {
-    pub fn from_string(s: String) -> Self {
-        let bytes = FigBuf::from_vec(s.into_bytes());
+impl FigBuf<[u8]> {
+    pub fn is_aligned(&self, align: usize) -> bool {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        self.as_slice().as_ptr() as usize & (align - 1) == 0
+    }
+
+    pub fn common_prefix_len(&self, other: &[u8]) -> usize {
+        common_prefix_len(self.as_slice(), other)
+    }
+
+    pub fn split_common_prefix(&self, other: &Self) -> (Self, Self, Self) {
+        let n = self.common_prefix_len(other.as_slice());
+        (self.slice(..n), self.slice(n..), other.slice(n..))
+    }
+
+    pub fn ensure_aligned(&mut self, align: usize) {
+        if self.is_aligned(align) {
+            return;
+        }
+
+        // Over-allocate and start the view at the first aligned byte; the
+        // Arc never moves its data, so the alignment holds for every clone.
+        // The view does not start at offset 0, so a later `make_mut` copies
+        // into a fresh allocation that is not necessarily aligned.
+        let len = self.len;
+        let capacity = (align - 1)
+            .checked_add(len)
+            .filter(|&capacity| capacity <= isize::MAX as usize)
+            .expect("capacity overflow");
+        let mut padded = Self::zeroed(capacity);
+        let addr = padded.as_slice().as_ptr() as usize;
+        let pad = (align - (addr & (align - 1))) & (align - 1);
+        padded.get_mut().expect("freshly allocated")[pad..pad + len]
+            .copy_from_slice(self.as_slice());
+
+        *self = padded.slice(pad..pad + len);
+    }
+
+    pub(crate) fn zeroed(len: usize) -> Self {
+        record_alloc();
         Self {
-            inner: match bytes.inner {
-                Inner::Arc(arc) => {
-                    Inner::Arc(unsafe { Arc::from_raw(Arc::into_raw(arc) as *const str) })
+            inner: ManuallyDrop::new(Inner::Arc((0..len).map(|_| 0).collect())),
+            offset: 0,
+            len,
+        }
+    }
+
+    // Zero-filled storage that a `BytesMut` can write into after splitting.
+    pub(crate) fn writable(len: usize) -> Self {
+        record_alloc();
+        Self {
+            inner: ManuallyDrop::new(Inner::Writable(Writable::from_arc(
+                (0..len).map(|_| 0).collect(),
+            ))),
+            offset: 0,
+            len,
+        }
+    }
+
+    // Turns a uniquely owned heap buffer into writable storage without
+    // copying, keeping the same view.
+    pub(crate) fn into_writable(mut self) -> Result<Self, Self> {
+        if self.get_mut().is_none() {
+            return Err(self);
+        }
+
+        let inner = std::mem::replace(&mut *self.inner, Inner::Static(&[]));
+        let inner = match inner {
+            Inner::Arc(arc) => Inner::Writable(Writable::from_arc(arc)),
+            inner => inner,
+        };
+        Ok(Self {
+            inner: ManuallyDrop::new(inner),
+            offset: self.offset,
+            len: self.len,
+        })
+    }
+
+    // Writable pointer to the start of the view. Callers must own the viewed
+    // range exclusively, as `BytesMut` does for its region of a shared
+    // allocation.
+    pub(crate) fn as_mut_ptr(&self) -> *mut u8 {
+        match &*self.inner {
+            // SAFETY: the view is in bounds of the allocation.
+            Inner::Writable(arc) => unsafe { (arc.as_ptr() as *mut u8).add(self.offset) },
+            // An empty `BytesMut` has nothing to write to.
+            Inner::Static(s) if self.len == 0 => s.as_ptr() as *mut u8,
+            _ => unreachable!("only writable storage is written"),
+        }
+    }
+
+    // Widens the view to the whole allocation, moving the first `keep` bytes
+    // to its start, if the allocation is unique and at least `needed` bytes.
+    pub(crate) fn reclaim_allocation(&mut self, keep: usize, needed: usize) -> bool {
+        let offset = self.offset;
+        match &mut *self.inner {
+            Inner::Writable(arc) => match Arc::get_mut(arc).map(Writable::get_mut) {
+                Some(full) if full.len() >= needed => {
+                    full.copy_within(offset..offset + keep, 0);
+                    self.offset = 0;
+                    self.len = full.len();
+                    true
                 }
-                Inner::Static(_) => unreachable!("from_vec never returns Static"),
+                _ => false,
             },
+            _ => false,
+        }
+    }
+}
+
+impl FigBuf<str> {
+    pub fn from_string(s: String) -> Self {
+        let len = s.len();
+        record_alloc();
+        Self {
+            inner: ManuallyDrop::new(Inner::Arc(Arc::from(s))),
             offset: 0,
-            len: bytes.len,
+            len,
         }
     }
 
     pub fn from_static(s: &'static str) -> Self {
         Self {
-            inner: Inner::Static(s),
+            inner: ManuallyDrop::new(Inner::Static(s)),
             offset: 0,
             len: s.len(),
         }
     }
 
+    pub fn from_owner<O>(owner: O) -> Self
+    where
+        O: AsRef<str> + Send + Sync + RefUnwindSafe + 'static,
+    {
+        let len = owner.as_ref().len();
+        Self {
+            inner: ManuallyDrop::new(Inner::Owner(Arc::new(owner))),
+            offset: 0,
+            len,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -206,37 +492,58 @@ impl FigBuf<str> {
     }
 
     pub fn as_str(&self) -> &str {
-        let full_str = match &self.inner {
+        let full_str = match &*self.inner {
             Inner::Static(s) => s,
             Inner::Arc(arc) => &**arc,
+            Inner::Owner(owner) => (**owner).as_ref(),
+            // SAFETY: the view is in bounds and on char boundaries, and
+            // nothing writes to it while this buffer can read it.
+            Inner::Writable(arc) => unsafe {
+                return std::str::from_utf8_unchecked(std::slice::from_raw_parts(
+                    (arc.as_ptr() as *const u8).add(self.offset),
+                    self.len,
+                ));
+            },
         };
         &full_str[self.offset..self.offset + self.len]
     }
 
     pub fn ref_count(&self) -> usize {
-        match &self.inner {
+        match &*self.inner {
             Inner::Static(_) => usize::MAX,
             Inner::Arc(arc) => Arc::strong_count(arc),
+            Inner::Owner(owner) => Arc::strong_count(owner),
+            Inner::Writable(arc) => Arc::strong_count(arc),
         }
     }
 
     pub fn try_mut(&mut self) -> Option<&mut str> {
-        match &mut self.inner {
-            Inner::Static(_) => None,
+        match &mut *self.inner {
+            Inner::Static(_) | Inner::Owner(_) => None,
             Inner::Arc(arc) => Arc::get_mut(arc).map(|s| unsafe {
                 let bytes = s.as_bytes_mut();
                 let slice = &mut bytes[self.offset..self.offset + self.len];
                 std::str::from_utf8_unchecked_mut(slice)
             }),
+            Inner::Writable(arc) => Arc::get_mut(arc).map(|data| unsafe {
+                let s = data.get_mut();
+                let bytes = s.as_bytes_mut();
+                let slice = &mut bytes[self.offset..self.offset + self.len];
+                std::str::from_utf8_unchecked_mut(slice)
+            }),
         }
     }
 
     pub fn make_mut(&mut self) -> &mut str {
-        let needs_clone = match &self.inner {
-            Inner::Static(_) => true,
+        let needs_clone = match &mut *self.inner {
+            Inner::Static(_) | Inner::Owner(_) => true,
             Inner::Arc(arc) => {
-                self.offset != 0 || self.len != arc.len() || Arc::strong_count(arc) > 1
+                self.offset != 0 || self.len != arc.len() || Arc::get_mut(arc).is_none()
             }
+            Inner::Writable(arc) => match Arc::get_mut(arc) {
+                Some(data) => self.offset != 0 || self.len != data.get_mut().len(),
+                None => true,
+            },
         };
 
         if needs_clone {
@@ -248,7 +555,29 @@ impl FigBuf<str> {
     }
 
     pub fn is_static(&self) -> bool {
-        matches!(&self.inner, Inner::Static(_))
+        matches!(&*self.inner, Inner::Static(_))
+    }
+
+    pub fn common_prefix_len(&self, other: &str) -> usize {
+        let mut n = common_prefix_len(self.as_str().as_bytes(), other.as_bytes());
+        while !self.as_str().is_char_boundary(n) {
+            n -= 1;
+        }
+        n
+    }
+
+    pub fn split_common_prefix(&self, other: &Self) -> (Self, Self, Self) {
+        let n = self.common_prefix_len(other.as_str());
+        (self.slice(..n), self.slice(n..), other.slice(n..))
+    }
+
+    pub fn reclaim_handle(&self) -> Option<ReclaimHandle<str>> {
+        match &*self.inner {
+            Inner::Static(_) => None,
+            Inner::Arc(arc) => Some(ReclaimHandle::new(Arc::downgrade(arc))),
+            Inner::Owner(owner) => Some(ReclaimHandle::from_owner(Arc::downgrade(owner))),
+            Inner::Writable(arc) => Some(ReclaimHandle::from_writable(Arc::downgrade(arc))),
+        }
     }
 }
 
@@ -409,6 +738,22 @@ impl Read for FigBuf<[u8]> {
         *self = self.slice(len..);
         Ok(len)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        let data = self.as_slice();
+        let mut total = 0;
+        for buf in bufs {
+            let remaining = &data[total..];
+            if remaining.is_empty() {
+                break;
+            }
+            let len = std::cmp::min(buf.len(), remaining.len());
+            buf[..len].copy_from_slice(&remaining[..len]);
+            total += len;
+        }
+        *self = self.slice(total..);
+        Ok(total)
+    }
 }
 
 impl Write for FigBuf<[u8]> {
@@ -439,6 +784,83 @@ impl Write for FigBuf<[u8]> {
     }
 }
 
+pub struct RChunks<T: 'static> {
+    buf: FigBuf<[T]>,
+    chunk_size: usize,
+}
+
+impl<T: 'static> Iterator for RChunks<T> {
+    type Item = FigBuf<[T]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let start = self.buf.len().saturating_sub(self.chunk_size);
+        let chunk = self.buf.slice(start..);
+        self.buf = self.buf.slice(..start);
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = match self.buf.len() % self.chunk_size {
+            0 => self.buf.len() / self.chunk_size,
+            _ => self.buf.len() / self.chunk_size + 1,
+        };
+        (n, Some(n))
+    }
+}
+
+impl<T: 'static> DoubleEndedIterator for RChunks<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let end = match self.buf.len() % self.chunk_size {
+            0 => self.chunk_size,
+            remainder => remainder,
+        };
+        let chunk = self.buf.slice(..end);
+        self.buf = self.buf.slice(end..);
+        Some(chunk)
+    }
+}
+
+impl<T: 'static> ExactSizeIterator for RChunks<T> {}
+
+pub struct RSplit<T: 'static, F> {
+    buf: FigBuf<[T]>,
+    pred: F,
+    finished: bool,
+}
+
+impl<T: 'static, F> Iterator for RSplit<T, F>
+where
+    F: FnMut(&T) -> bool,
+{
+    type Item = FigBuf<[T]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.buf.as_slice().iter().rposition(&mut self.pred) {
+            Some(idx) => {
+                let tail = self.buf.slice(idx + 1..);
+                self.buf = self.buf.slice(..idx);
+                Some(tail)
+            }
+            None => {
+                self.finished = true;
+                Some(self.buf.clone())
+            }
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
 mod serde_impl {
     use super::*;
@@ -489,6 +911,66 @@ mod serde_impl {
     }
 }
 
+#[cfg(feature = "normalize")]
+mod normalize_impl {
+    use super::*;
+    use unicode_normalization::{is_nfc, is_nfd, is_nfkc, is_nfkd, UnicodeNormalization};
+
+    impl FigBuf<str> {
+        pub fn nfc(&self) -> Self {
+            if is_nfc(self.as_str()) {
+                return self.clone();
+            }
+            Self::from_string(self.as_str().nfc().collect())
+        }
+
+        pub fn nfd(&self) -> Self {
+            if is_nfd(self.as_str()) {
+                return self.clone();
+            }
+            Self::from_string(self.as_str().nfd().collect())
+        }
+
+        pub fn nfkc(&self) -> Self {
+            if is_nfkc(self.as_str()) {
+                return self.clone();
+            }
+            Self::from_string(self.as_str().nfkc().collect())
+        }
+
+        pub fn nfkd(&self) -> Self {
+            if is_nfkd(self.as_str()) {
+                return self.clone();
+            }
+            Self::from_string(self.as_str().nfkd().collect())
+        }
+    }
+}
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+mod uring_impl {
+    use super::*;
+    use tokio_uring::buf::IoBuf;
+
+    // SAFETY: the bytes live in a static, an `Arc` allocation or memory borrowed
+    // from a shared owner, none of which move with the `FigBuf`, and all of
+    // which stay alive for as long as it does. Frozen `BytesMut` ranges are
+    // no longer written by anyone.
+    unsafe impl IoBuf for FigBuf<[u8]> {
+        fn stable_ptr(&self) -> *const u8 {
+            self.as_slice().as_ptr()
+        }
+
+        fn bytes_init(&self) -> usize {
+            self.len
+        }
+
+        fn bytes_total(&self) -> usize {
+            self.len
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1026,6 +1508,79 @@ mod tests {
         assert_eq!(&output3[..2], &[7, 8]);
     }
 
+    #[test]
+    fn test_read_vectored() {
+        use std::io::{IoSliceMut, Read};
+
+        let mut buf = FigBuf::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut a = [0u8; 3];
+        let mut b = [0u8; 0];
+        let mut c = [0u8; 2];
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut b),
+            IoSliceMut::new(&mut c),
+        ];
+
+        assert_eq!(buf.read_vectored(&mut bufs).unwrap(), 5);
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(c, [4, 5]);
+        assert_eq!(buf.as_slice(), &[6, 7, 8]);
+    }
+
+    #[test]
+    fn test_read_vectored_partial() {
+        use std::io::{IoSliceMut, Read};
+
+        let mut buf = FigBuf::from_vec(vec![1, 2, 3, 4]);
+        let mut a = [0u8; 3];
+        let mut b = [0u8; 3];
+        let mut c = [0u8; 3];
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut b),
+            IoSliceMut::new(&mut c),
+        ];
+
+        assert_eq!(buf.read_vectored(&mut bufs).unwrap(), 4);
+        assert!(buf.is_empty());
+        assert_eq!(buf.read_vectored(&mut bufs).unwrap(), 0);
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(b, [4, 0, 0]);
+        assert_eq!(c, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_vectored_repeated() {
+        use std::io::{IoSliceMut, Read};
+
+        let data: Vec<u8> = (0..=255).cycle().take(1_000).collect();
+        let mut reader = FigBuf::from_vec(data.clone());
+        let mut out = Vec::new();
+
+        // 7 + 0 + 5 bytes per call, so most reads stop mid-buffer and the
+        // next call starts where the previous one left off.
+        loop {
+            let mut a = [0u8; 7];
+            let mut b = [0u8; 0];
+            let mut c = [0u8; 5];
+            let mut bufs = [
+                IoSliceMut::new(&mut a),
+                IoSliceMut::new(&mut b),
+                IoSliceMut::new(&mut c),
+            ];
+            let n = reader.read_vectored(&mut bufs).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&a[..n.min(7)]);
+            out.extend_from_slice(&c[..n.saturating_sub(7)]);
+            assert_eq!(reader.len(), data.len() - out.len());
+        }
+
+        assert_eq!(out, data);
+    }
+
     #[test]
     fn test_write_trait() {
         use std::io::Write;
@@ -1194,4 +1749,435 @@ mod tests {
         let json = serde_json::to_string(&buf).unwrap();
         assert_eq!(json, "[0,1,2,255]");
     }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_nfc_already_normalized_shares_buffer() {
+        let buf = FigBuf::from_string(String::from("caf\u{e9}"));
+        let normalized = buf.nfc();
+
+        assert_eq!(normalized.as_str(), "caf\u{e9}");
+        assert_eq!(buf.ref_count(), 2);
+        assert_eq!(normalized.as_str().as_ptr(), buf.as_str().as_ptr());
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_nfc_composes() {
+        let buf = FigBuf::from_string(String::from("cafe\u{301}"));
+        let normalized = buf.nfc();
+
+        assert_eq!(normalized.as_str(), "caf\u{e9}");
+        assert_eq!(buf.ref_count(), 1);
+        assert_eq!(normalized.ref_count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_nfd_decomposes() {
+        let buf = FigBuf::<str>::from_static("caf\u{e9}");
+        let normalized = buf.nfd();
+
+        assert_eq!(normalized.as_str(), "cafe\u{301}");
+        assert!(!normalized.is_static());
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_nfkc_compatibility() {
+        let buf = FigBuf::from_string(String::from("\u{fb01}le"));
+        assert_eq!(buf.nfkc().as_str(), "file");
+        assert_eq!(buf.nfc().as_str(), "\u{fb01}le");
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_nfkd_compatibility() {
+        let buf = FigBuf::from_string(String::from("\u{2460}\u{e9}"));
+        assert_eq!(buf.nfkd().as_str(), "1e\u{301}");
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_normalize_static_ascii_stays_static() {
+        let buf = FigBuf::<str>::from_static("plain ascii");
+
+        assert!(buf.nfc().is_static());
+        assert!(buf.nfd().is_static());
+        assert!(buf.nfkc().is_static());
+        assert!(buf.nfkd().is_static());
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn test_normalize_slice() {
+        let buf = FigBuf::from_string(String::from("id: cafe\u{301}"));
+        let value = buf.slice(4..);
+
+        assert_eq!(value.nfc().as_str(), "caf\u{e9}");
+        assert_eq!(value.nfc(), FigBuf::from("caf\u{e9}").nfc());
+    }
+
+    #[test]
+    fn test_rchunks() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3, 4, 5, 6, 7]);
+        let chunks: Vec<_> = buf.rchunks(3).collect();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].as_slice(), &[5, 6, 7]);
+        assert_eq!(chunks[1].as_slice(), &[2, 3, 4]);
+        assert_eq!(chunks[2].as_slice(), &[1]);
+        assert_eq!(buf.ref_count(), 4);
+    }
+
+    #[test]
+    fn test_rchunks_matches_slice_rchunks() {
+        let data: Vec<u8> = (0..23).collect();
+        let buf = FigBuf::from_vec(data.clone());
+
+        for size in 1..=25 {
+            let ours: Vec<Vec<u8>> = buf.rchunks(size).map(|c| c.to_vec()).collect();
+            let std: Vec<Vec<u8>> = data.rchunks(size).map(|c| c.to_vec()).collect();
+            assert_eq!(ours, std);
+
+            let ours: Vec<Vec<u8>> = buf.rchunks(size).rev().map(|c| c.to_vec()).collect();
+            let std: Vec<Vec<u8>> = data.rchunks(size).rev().map(|c| c.to_vec()).collect();
+            assert_eq!(ours, std);
+        }
+    }
+
+    #[test]
+    fn test_rchunks_exact_size() {
+        let buf = FigBuf::from_vec(vec![0u8; 10]);
+        let mut chunks = buf.rchunks(4);
+
+        assert_eq!(chunks.len(), 3);
+        chunks.next();
+        assert_eq!(chunks.len(), 2);
+        chunks.next_back();
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn test_rchunks_zero_size() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3]);
+        let _ = buf.rchunks(0);
+    }
+
+    #[test]
+    fn test_rsplit() {
+        let buf = FigBuf::from_vec(vec![1, 0, 2, 3, 0, 4]);
+        let parts: Vec<_> = buf.rsplit(|&x| x == 0).collect();
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].as_slice(), &[4]);
+        assert_eq!(parts[1].as_slice(), &[2, 3]);
+        assert_eq!(parts[2].as_slice(), &[1]);
+    }
+
+    #[test]
+    fn test_rsplit_matches_slice_rsplit() {
+        let inputs: [&[u8]; 5] = [b"", b",", b"a,,b", b",a,b,", b"abc"];
+
+        for input in inputs {
+            let buf = FigBuf::from_vec(input.to_vec());
+            let ours: Vec<Vec<u8>> = buf.rsplit(|&b| b == b',').map(|p| p.to_vec()).collect();
+            let std: Vec<Vec<u8>> = input.rsplit(|&b| b == b',').map(|p| p.to_vec()).collect();
+            assert_eq!(ours, std);
+        }
+    }
+
+    #[test]
+    fn test_rsplit_static() {
+        static LOG: [u8; 11] = *b"one\ntwo\nend";
+        let buf = FigBuf::<[u8]>::from_static(&LOG);
+        let last = buf.rsplit(|&b| b == b'\n').next().unwrap();
+
+        assert_eq!(last.as_slice(), b"end");
+        assert!(last.is_static());
+    }
+
+    #[test]
+    fn test_ensure_aligned() {
+        let buf = FigBuf::from_vec((0..100u8).collect());
+
+        for align in [1, 2, 8, 64, 512, 4096] {
+            let mut slice = buf.slice(3..67);
+            slice.ensure_aligned(align);
+
+            assert!(slice.is_aligned(align));
+            assert_eq!(slice.as_slice().as_ptr() as usize % align, 0);
+            assert_eq!(slice.as_slice(), &buf.as_slice()[3..67]);
+        }
+    }
+
+    #[test]
+    fn test_ensure_aligned_noop_when_aligned() {
+        let buf = FigBuf::from_vec(vec![1u8, 2, 3, 4]);
+        let mut clone = buf.clone();
+        clone.ensure_aligned(1);
+
+        assert_eq!(buf.ref_count(), 2);
+        assert_eq!(clone.as_slice().as_ptr(), buf.as_slice().as_ptr());
+    }
+
+    #[test]
+    fn test_ensure_aligned_survives_slice_and_clone() {
+        let mut buf = FigBuf::from_vec(vec![7u8; 8192]);
+        buf = buf.slice(1..);
+        buf.ensure_aligned(4096);
+
+        let clone = buf.clone();
+        assert!(clone.is_aligned(4096));
+        assert!(buf.slice(4096..).is_aligned(4096));
+        assert_eq!(buf.len(), 8191);
+    }
+
+    #[test]
+    fn test_ensure_aligned_get_mut() {
+        let mut buf = FigBuf::from_vec(vec![0u8; 32]).slice(1..17);
+        buf.ensure_aligned(16);
+
+        let slice = buf.get_mut().expect("realigned buffer is uniquely owned");
+        slice[0] = 42;
+        assert_eq!(buf[0], 42);
+        assert!(buf.is_aligned(16));
+    }
+
+    #[test]
+    fn test_ensure_aligned_static() {
+        #[repr(align(64))]
+        struct Aligned([u8; 16]);
+        static DATA: Aligned = Aligned([9; 16]);
+
+        let mut buf = FigBuf::<[u8]>::from_static(&DATA.0);
+        buf.ensure_aligned(64);
+        assert!(buf.is_static());
+
+        let mut buf = buf.slice(1..);
+        buf.ensure_aligned(64);
+        assert!(buf.is_aligned(64));
+        assert!(!buf.is_static());
+        assert_eq!(buf.as_slice(), &DATA.0[1..]);
+    }
+
+    #[test]
+    fn test_ensure_aligned_empty() {
+        let mut buf = FigBuf::from_vec(Vec::<u8>::new());
+        buf.ensure_aligned(256);
+
+        assert!(buf.is_aligned(256));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "alignment must be a power of two")]
+    fn test_ensure_aligned_invalid() {
+        let mut buf = FigBuf::from_vec(vec![1u8, 2, 3]);
+        buf.ensure_aligned(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_ensure_aligned_overflow() {
+        let mut buf = FigBuf::from_vec(vec![1u8, 2, 3]).slice(1..);
+        buf.ensure_aligned(1 << (usize::BITS - 1));
+    }
+
+    #[test]
+    fn test_common_prefix_len_helper() {
+        let a: Vec<u8> = (0..40).collect();
+
+        for split in 0..=40 {
+            let mut b = a.clone();
+            if split < b.len() {
+                b[split] ^= 0x80;
+            }
+            assert_eq!(common_prefix_len(&a, &b), split);
+            assert_eq!(common_prefix_len(&b, &a), split);
+            assert_eq!(common_prefix_len(&a[..split], &a), split);
+        }
+
+        assert_eq!(common_prefix_len(b"", b"abc"), 0);
+    }
+
+    #[test]
+    fn test_split_common_prefix_bytes() {
+        let a = FigBuf::from_vec(b"/api/v1/users".to_vec());
+        let b = FigBuf::from_vec(b"/api/v2/users".to_vec());
+
+        assert_eq!(a.common_prefix_len(&b), 6);
+
+        let (prefix, a_rest, b_rest) = a.split_common_prefix(&b);
+        assert_eq!(prefix.as_slice(), b"/api/v");
+        assert_eq!(a_rest.as_slice(), b"1/users");
+        assert_eq!(b_rest.as_slice(), b"2/users");
+        assert_eq!(a.ref_count(), 3);
+        assert_eq!(b.ref_count(), 2);
+    }
+
+    #[test]
+    fn test_split_common_prefix_one_is_prefix() {
+        let a = FigBuf::from_vec(b"romane".to_vec());
+        let b = FigBuf::from_vec(b"roman".to_vec());
+
+        let (prefix, a_rest, b_rest) = a.split_common_prefix(&b);
+        assert_eq!(prefix.as_slice(), b"roman");
+        assert_eq!(a_rest.as_slice(), b"e");
+        assert!(b_rest.is_empty());
+    }
+
+    #[test]
+    fn test_common_prefix_len_str_char_boundary() {
+        // "\u{e9}" is C3 A9 and "\u{e8}" is C3 A8: the shared C3 byte is not
+        // a whole character.
+        let a = FigBuf::from_string(String::from("caf\u{e9}"));
+        let b = FigBuf::from_string(String::from("caf\u{e8}"));
+
+        assert_eq!(a.common_prefix_len(&b), 3);
+
+        let (prefix, a_rest, b_rest) = a.split_common_prefix(&b);
+        assert_eq!(prefix.as_str(), "caf");
+        assert_eq!(a_rest.as_str(), "\u{e9}");
+        assert_eq!(b_rest.as_str(), "\u{e8}");
+    }
+
+    #[test]
+    fn test_split_common_prefix_str_static() {
+        let a = FigBuf::<str>::from_static("interning");
+        let b = FigBuf::<str>::from_static("internet");
+
+        let (prefix, a_rest, b_rest) = a.split_common_prefix(&b);
+        assert_eq!(prefix.as_str(), "intern");
+        assert_eq!(a_rest.as_str(), "ing");
+        assert_eq!(b_rest.as_str(), "et");
+        assert!(prefix.is_static());
+    }
+
+    #[test]
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    fn test_io_buf() {
+        use tokio_uring::buf::IoBuf;
+
+        let buf = FigBuf::from_vec(vec![1u8, 2, 3, 4, 5]);
+        let buf = FigBuf::<[u8]>::slice(&buf, 1..4);
+
+        assert_eq!(buf.stable_ptr(), buf.as_slice().as_ptr());
+        assert_eq!(buf.bytes_init(), 3);
+        assert_eq!(buf.bytes_total(), 3);
+
+        let slice = IoBuf::slice(buf, 1..);
+        assert_eq!(&slice[..], &[3, 4]);
+
+        let buf = slice.into_inner();
+        assert_eq!(buf.as_slice(), &[2, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    fn test_io_buf_static() {
+        use tokio_uring::buf::IoBuf;
+
+        static DATA: [u8; 4] = *b"fig!";
+        let buf = FigBuf::<[u8]>::from_static(&DATA);
+
+        assert_eq!(buf.stable_ptr(), DATA.as_ptr());
+        assert_eq!(buf.bytes_init(), 4);
+    }
+
+    struct TrackedOwner {
+        data: Vec<u8>,
+        dropped: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl AsRef<[u8]> for TrackedOwner {
+        fn as_ref(&self) -> &[u8] {
+            &self.data
+        }
+    }
+
+    impl Drop for TrackedOwner {
+        fn drop(&mut self) {
+            self.dropped
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_from_owner_keeps_owner_alive() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let buf = FigBuf::<[u8]>::from_owner(TrackedOwner {
+            data: vec![1, 2, 3, 4, 5],
+            dropped: Arc::clone(&dropped),
+        });
+        let ptr = buf.as_slice().as_ptr();
+
+        let tail = buf.slice(2..);
+        let clone = buf.clone();
+        assert_eq!(tail.as_slice(), &[3, 4, 5]);
+        assert_eq!(tail.as_slice().as_ptr(), unsafe { ptr.add(2) });
+        assert_eq!(buf.ref_count(), 3);
+        assert!(!buf.is_static());
+
+        drop(buf);
+        drop(clone);
+        assert!(!dropped.load(Ordering::SeqCst));
+        assert_eq!(tail.ref_count(), 1);
+
+        drop(tail);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_from_owner_unwind_safe() {
+        fn assert_unwind_safe<T: std::panic::UnwindSafe + RefUnwindSafe>(_: &T) {}
+
+        let buf = FigBuf::<[u8]>::from_owner(vec![1, 2, 3]);
+        assert_unwind_safe(&buf);
+        assert_unwind_safe(&FigBuf::<str>::from_owner(String::from("owned")));
+
+        let sum = std::panic::catch_unwind(|| buf.iter().map(|&b| b as u32).sum::<u32>());
+        assert_eq!(sum.unwrap(), 6);
+    }
+
+    #[test]
+    fn test_from_owner_copy_on_write() {
+        let mut buf = FigBuf::<[u8]>::from_owner(vec![1, 2, 3]);
+        let original = buf.clone();
+
+        assert!(buf.get_mut().is_none());
+        buf.make_mut()[0] = 10;
+
+        assert_eq!(buf.as_slice(), &[10, 2, 3]);
+        assert_eq!(original.as_slice(), &[1, 2, 3]);
+        assert!(buf.get_mut().is_some());
+    }
+
+    #[test]
+    fn test_from_owner_str() {
+        let mut s = FigBuf::<str>::from_owner(String::from("hello world"));
+        let world = s.slice(6..);
+
+        assert_eq!(world.as_str(), "world");
+        assert!(s.try_mut().is_none());
+        s.make_mut().make_ascii_uppercase();
+        assert_eq!(s.as_str(), "HELLO WORLD");
+        assert_eq!(world.as_str(), "world");
+    }
+
+    #[test]
+    fn test_from_owner_reclaim_handle() {
+        let buf = FigBuf::<[u8]>::from_owner(vec![0u8; 8]);
+        let reader = buf.slice(4..);
+        let handle = buf.reclaim_handle().unwrap();
+
+        assert_eq!(handle.ref_count(), 2);
+        let t = std::thread::spawn(move || drop(reader));
+        handle.wait();
+        t.join().unwrap();
+        assert_eq!(buf.ref_count(), 1);
+    }
 }
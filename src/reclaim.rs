@@ -0,0 +1,497 @@
+//! Notification when a shared allocation becomes uniquely owned again.
+//!
+//! A `ReclaimHandle` watches one heap allocation behind a `FigBuf` without
+//! keeping it alive. It becomes ready once at most one `FigBuf` still refers
+//! to the allocation, so buffer pools and double-buffering schemes can reuse
+//! memory as soon as readers are done instead of polling `ref_count()`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::{Owner, Writable};
+
+/// Number of registered waiters, checked on every drop before taking the lock.
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+
+/// Registered wakers, keyed by the address of the allocation they watch.
+static WAITERS: Mutex<BTreeMap<usize, Vec<Waiter>>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct Waiter {
+    id: usize,
+    waker: Waker,
+}
+
+fn waiters() -> MutexGuard<'static, BTreeMap<usize, Vec<Waiter>>> {
+    WAITERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Wakes every handle watching the allocation at `addr`.
+///
+/// Called after a `FigBuf` has released its reference. Woken handles re-check
+/// the reference count themselves, so spurious wakeups are harmless.
+pub(crate) fn notify(addr: usize) {
+    // The buffer's reference was released with a Release read-modify-write
+    // of the count. If a handle re-checked the count before that (see
+    // `recheck`), its own release is acquired here, so its registration is
+    // visible; otherwise the handle saw the decremented count itself. On
+    // most targets this fence costs nothing.
+    fence(Ordering::Acquire);
+    if WAITING.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    let woken = match waiters().remove(&addr) {
+        Some(woken) => woken,
+        None => return,
+    };
+    WAITING.fetch_sub(woken.len(), Ordering::Relaxed);
+
+    for waiter in woken {
+        waiter.waker.wake();
+    }
+}
+
+/// A handle that becomes ready when a buffer's allocation is uniquely owned.
+///
+/// Obtained from `FigBuf::reclaim_handle`. The handle holds only a weak
+/// reference, so it does not count towards `ref_count()` and does not keep
+/// the data alive.
+///
+/// The handle can be checked with [`is_reclaimable`](Self::is_reclaimable),
+/// awaited as a `Future`, or waited on synchronously with
+/// [`wait`](Self::wait).
+///
+/// Until it resolves, the handle's weak reference makes `get_mut` and
+/// `try_mut` return `None` and `make_mut` copy. Once it has reported the
+/// allocation as reclaimable it lets go of it, so the buffer can be reused in
+/// place even if the handle is kept around.
+///
+/// Polling takes and releases a temporary reference to re-check the count,
+/// so a concurrent `ref_count()` on a buffer may briefly be one higher.
+///
+/// # Example
+///
+/// ```
+/// use fig::FigBuf;
+///
+/// let buf = FigBuf::from_vec(vec![1u8, 2, 3]);
+/// let reader = buf.slice(1..);
+/// let handle = buf.reclaim_handle().unwrap();
+///
+/// assert!(!handle.is_reclaimable());
+///
+/// let t = std::thread::spawn(move || drop(reader));
+/// handle.wait();
+/// t.join().unwrap();
+///
+/// assert_eq!(buf.ref_count(), 1);
+/// ```
+pub struct ReclaimHandle<T: ?Sized + 'static> {
+    // `None` once the handle has resolved.
+    target: Option<Target<T>>,
+    addr: usize,
+    id: usize,
+    registered: bool,
+}
+
+enum Target<T: ?Sized + 'static> {
+    Arc(Weak<T>),
+    Owner(Weak<Owner<T>>),
+    Writable(Weak<Writable<T>>),
+}
+
+impl<T: ?Sized + 'static> ReclaimHandle<T> {
+    pub(crate) fn new(weak: Weak<T>) -> Self {
+        Self::with_target(Target::Arc(weak))
+    }
+
+    pub(crate) fn from_owner(weak: Weak<Owner<T>>) -> Self {
+        Self::with_target(Target::Owner(weak))
+    }
+
+    pub(crate) fn from_writable(weak: Weak<Writable<T>>) -> Self {
+        Self::with_target(Target::Writable(weak))
+    }
+
+    fn with_target(target: Target<T>) -> Self {
+        let addr = match &target {
+            Target::Arc(weak) => Weak::as_ptr(weak) as *const () as usize,
+            Target::Owner(weak) => Weak::as_ptr(weak) as *const () as usize,
+            Target::Writable(weak) => Weak::as_ptr(weak) as *const () as usize,
+        };
+        Self {
+            target: Some(target),
+            addr,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            registered: false,
+        }
+    }
+
+    /// Returns the number of buffers still referring to the allocation.
+    ///
+    /// Returns 0 once the handle has resolved and stopped tracking it.
+    pub fn ref_count(&self) -> usize {
+        match &self.target {
+            Some(Target::Arc(weak)) => weak.strong_count(),
+            Some(Target::Owner(weak)) => weak.strong_count(),
+            Some(Target::Writable(weak)) => weak.strong_count(),
+            None => 0,
+        }
+    }
+
+    /// Returns `true` if at most one buffer still refers to the allocation.
+    pub fn is_reclaimable(&self) -> bool {
+        self.ref_count() <= 1
+    }
+
+    /// Polls for the allocation to become uniquely owned.
+    ///
+    /// Registers the current task to be woken when a buffer sharing the
+    /// allocation is dropped.
+    pub fn poll_reclaimable(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_reclaimable() {
+            self.resolve();
+            return Poll::Ready(());
+        }
+
+        // Dropping a waker may release a buffer, whose drop calls `notify`, so
+        // the old waker is only dropped once the lock is released.
+        let mut replaced = None;
+        {
+            let mut waiters = waiters();
+            let watching = waiters.entry(self.addr).or_default();
+            match watching.iter_mut().find(|waiter| waiter.id == self.id) {
+                Some(waiter) => {
+                    if !waiter.waker.will_wake(cx.waker()) {
+                        replaced = Some(mem::replace(&mut waiter.waker, cx.waker().clone()));
+                    }
+                }
+                None => {
+                    watching.push(Waiter {
+                        id: self.id,
+                        waker: cx.waker().clone(),
+                    });
+                    WAITING.fetch_add(1, Ordering::Relaxed);
+                    self.registered = true;
+                }
+            }
+        }
+        drop(replaced);
+
+        // Re-check in case the last other reference was dropped before the
+        // registration became visible to `notify`.
+        if self.recheck() {
+            self.resolve();
+            // Other handles may have counted the reference taken by `recheck`
+            // and gone back to sleep.
+            notify(self.addr);
+            return Poll::Ready(());
+        }
+
+        Poll::Pending
+    }
+
+    // Reads the reference count through a read-modify-write of it: a
+    // temporary strong reference is taken and released again. A buffer
+    // released after that acquires the release in `notify` and sees the
+    // registration; one released before is already reflected in the count.
+    fn recheck(&self) -> bool {
+        match &self.target {
+            Some(Target::Arc(weak)) => drop(weak.upgrade()),
+            Some(Target::Owner(weak)) => drop(weak.upgrade()),
+            Some(Target::Writable(weak)) => drop(weak.upgrade()),
+            None => return true,
+        }
+        self.is_reclaimable()
+    }
+
+    /// Blocks the current thread until the allocation is uniquely owned.
+    pub fn wait(mut self) {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        while self.poll_reclaimable(&mut cx).is_pending() {
+            thread::park();
+        }
+    }
+
+    fn resolve(&mut self) {
+        self.unregister();
+        self.target = None;
+    }
+
+    fn unregister(&mut self) {
+        if !self.registered {
+            return;
+        }
+        self.registered = false;
+
+        let removed = {
+            let mut waiters = waiters();
+            let mut removed = None;
+            if let Some(watching) = waiters.get_mut(&self.addr) {
+                if let Some(pos) = watching.iter().position(|waiter| waiter.id == self.id) {
+                    removed = Some(watching.swap_remove(pos));
+                    WAITING.fetch_sub(1, Ordering::Relaxed);
+                }
+                if watching.is_empty() {
+                    waiters.remove(&self.addr);
+                }
+            }
+            removed
+        };
+        // Dropped outside the lock, like the old waker in `poll_reclaimable`.
+        drop(removed);
+    }
+}
+
+impl<T: ?Sized + 'static> Future for ReclaimHandle<T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.get_mut().poll_reclaimable(cx)
+    }
+}
+
+impl<T: ?Sized + 'static> Drop for ReclaimHandle<T> {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+impl<T: ?Sized + 'static> fmt::Debug for ReclaimHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReclaimHandle")
+            .field("ref_count", &self.ref_count())
+            .finish()
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FigBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_reclaim_unique_is_ready() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3]);
+        let handle = buf.reclaim_handle().unwrap();
+
+        assert!(handle.is_reclaimable());
+        assert_eq!(handle.ref_count(), 1);
+        assert_eq!(buf.ref_count(), 1);
+    }
+
+    #[test]
+    fn test_reclaim_static_has_no_handle() {
+        static DATA: [u8; 3] = [1, 2, 3];
+        let buf = FigBuf::<[u8]>::from_static(&DATA);
+
+        assert!(buf.reclaim_handle().is_none());
+        assert!(FigBuf::<str>::from_static("static")
+            .reclaim_handle()
+            .is_none());
+    }
+
+    #[test]
+    fn test_reclaim_poll_wakes_on_drop() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3, 4]);
+        let a = buf.slice(..2);
+        let b = buf.slice(2..);
+        let mut handle = buf.reclaim_handle().unwrap();
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+
+        drop(a);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+
+        drop(b);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn test_reclaim_make_mut_notifies() {
+        let mut buf = FigBuf::from_string(String::from("hello"));
+        let original = buf.clone();
+        let mut handle = original.reclaim_handle().unwrap();
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+
+        buf.make_mut().make_ascii_uppercase();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Ready(()));
+        assert_eq!(&*original, "hello");
+    }
+
+    #[test]
+    fn test_reclaim_wait_across_threads() {
+        let buf = FigBuf::from_vec(vec![0u8; 64]);
+        let readers: Vec<_> = (0..4).map(|i| buf.slice(i * 16..(i + 1) * 16)).collect();
+        let handle = buf.reclaim_handle().unwrap();
+
+        let threads: Vec<_> = readers
+            .into_iter()
+            .map(|reader| {
+                std::thread::spawn(move || reader.iter().map(|&b| b as usize).sum::<usize>())
+            })
+            .collect();
+
+        handle.wait();
+        assert_eq!(buf.ref_count(), 1);
+
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_reclaim_dropped_handle_unregisters() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3]);
+        let clone = buf.clone();
+        let mut handle = buf.reclaim_handle().unwrap();
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+        drop(handle);
+        drop(clone);
+
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_reclaim_make_mut_with_live_handle() {
+        let mut buf = FigBuf::from_vec(vec![1u8, 2, 3]);
+        let handle = buf.reclaim_handle().unwrap();
+
+        buf.make_mut()[0] = 10;
+        assert_eq!(buf.as_slice(), &[10, 2, 3]);
+        drop(handle);
+
+        let mut s = FigBuf::from_string(String::from("hello"));
+        let handle = s.reclaim_handle().unwrap();
+
+        s.make_mut().make_ascii_uppercase();
+        assert_eq!(&*s, "HELLO");
+        drop(handle);
+    }
+
+    #[test]
+    fn test_reclaim_resolved_handle_allows_get_mut() {
+        let mut buf = FigBuf::from_vec(vec![1u8, 2, 3]);
+        let reader = buf.clone();
+        let mut handle = buf.reclaim_handle().unwrap();
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+        assert!(buf.get_mut().is_none());
+
+        drop(reader);
+        assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Ready(()));
+        assert!(handle.is_reclaimable());
+
+        let ptr = buf.as_ptr();
+        buf.get_mut().unwrap()[0] = 10;
+        buf.make_mut()[1] = 20;
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf.as_slice(), &[10, 20, 3]);
+    }
+
+    #[test]
+    fn test_reclaim_waker_drop_releases_buffer() {
+        // A waker that owns a buffer releases it when the last clone drops.
+        struct BufferWaker {
+            _buf: FigBuf<[u8]>,
+        }
+
+        impl Wake for BufferWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let buf = FigBuf::from_vec(vec![1u8, 2, 3]);
+        let reader = buf.clone();
+        let mut first = buf.reclaim_handle().unwrap();
+        let mut second = buf.reclaim_handle().unwrap();
+
+        for handle in [&mut first, &mut second] {
+            let owned = FigBuf::from_vec(vec![0u8; 4]);
+            let waker = Waker::from(Arc::new(BufferWaker { _buf: owned }));
+            let mut cx = Context::from_waker(&waker);
+            assert_eq!(handle.poll_reclaimable(&mut cx), Poll::Pending);
+        }
+
+        // Replacing the registered waker drops the only other clone of it.
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(first.poll_reclaimable(&mut cx), Poll::Pending);
+
+        // So does unregistering, while `first` keeps `notify` taking the lock.
+        drop(second);
+
+        drop(reader);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(first.poll_reclaimable(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn test_reclaim_wait_races_drop() {
+        for _ in 0..50 {
+            let buf = FigBuf::from_vec(vec![0u8; 16]);
+            let reader = buf.clone();
+            let handle = buf.reclaim_handle().unwrap();
+
+            let t = std::thread::spawn(move || drop(reader));
+            handle.wait();
+            t.join().unwrap();
+            assert_eq!(buf.ref_count(), 1);
+        }
+    }
+}
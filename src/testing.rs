@@ -0,0 +1,185 @@
+//! Test helpers for enforcing zero-copy behavior.
+//!
+//! Every time fig creates a new backing allocation for a buffer it bumps a
+//! per-thread counter. The helpers here read that counter around a scope and
+//! fail the test if more allocations happened than expected.
+//!
+//! Only allocations made on the current thread are counted, so tests running
+//! in parallel do not affect each other.
+//!
+//! This module requires the `testing` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use fig::bytes::Bytes;
+//! use fig::testing::assert_no_alloc;
+//!
+//! let bytes = Bytes::from_vec(b"GET / HTTP/1.1\r\n\r\n".to_vec());
+//!
+//! let (method, rest) = assert_no_alloc(|| {
+//!     let mut rest = bytes.clone();
+//!     let method = rest.split_to(3);
+//!     (method, rest)
+//! });
+//!
+//! assert_eq!(&*method, b"GET");
+//! assert_eq!(rest.len(), bytes.len() - 3);
+//! ```
+
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+pub(crate) fn record_alloc() {
+    ALLOCATIONS.with(|count| count.set(count.get() + 1));
+}
+
+/// Returns the number of backing allocations made on this thread so far.
+pub fn allocation_count() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Runs `f` and returns its result along with the number of backing
+/// allocations it made.
+pub fn count_allocs<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = allocation_count();
+    let result = f();
+    (result, allocation_count() - before)
+}
+
+/// Runs `f`, panicking if it made any backing allocation.
+pub fn assert_no_alloc<R>(f: impl FnOnce() -> R) -> R {
+    assert_max_allocs(0, f)
+}
+
+/// Runs `f`, panicking if it made more than `max` backing allocations.
+pub fn assert_max_allocs<R>(max: usize, f: impl FnOnce() -> R) -> R {
+    let (result, count) = count_allocs(f);
+    assert!(
+        count <= max,
+        "expected at most {} backing allocation(s), but {} were made",
+        max,
+        count
+    );
+    result
+}
+
+/// A guard that checks the allocation count when it goes out of scope.
+///
+/// Useful when the code under test does not fit neatly into a closure.
+///
+/// # Example
+///
+/// ```
+/// use fig::FigBuf;
+/// use fig::testing::AllocGuard;
+///
+/// let buf = FigBuf::from_vec(vec![1, 2, 3, 4]);
+///
+/// let guard = AllocGuard::new(1);
+/// let mut copy = buf.slice(1..);
+/// copy.make_mut()[0] = 20;
+/// assert_eq!(guard.count(), 1);
+/// drop(guard);
+/// ```
+pub struct AllocGuard {
+    start: usize,
+    max: usize,
+}
+
+impl AllocGuard {
+    /// Starts counting, allowing at most `max` backing allocations.
+    pub fn new(max: usize) -> Self {
+        Self {
+            start: allocation_count(),
+            max,
+        }
+    }
+
+    /// Returns the number of backing allocations made since the guard was created.
+    pub fn count(&self) -> usize {
+        allocation_count() - self.start
+    }
+}
+
+impl Drop for AllocGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+
+        let count = self.count();
+        assert!(
+            count <= self.max,
+            "expected at most {} backing allocation(s), but {} were made",
+            self.max,
+            count
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FigBuf;
+
+    #[test]
+    fn test_count_allocs() {
+        let ((), count) = count_allocs(|| {
+            let _a = FigBuf::from_vec(vec![1, 2, 3]);
+            let _b = FigBuf::from_string(String::from("hello"));
+        });
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_slicing_does_not_allocate() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3, 4, 5]);
+
+        let slice = assert_no_alloc(|| buf.slice(1..4).clone().slice(1..));
+        assert_eq!(slice.as_slice(), &[3, 4]);
+    }
+
+    #[test]
+    fn test_static_does_not_allocate() {
+        assert_no_alloc(|| {
+            let buf = FigBuf::<str>::from_static("static");
+            buf.slice(1..3)
+        });
+    }
+
+    #[test]
+    fn test_make_mut_counts_copy() {
+        let buf = FigBuf::from_vec(vec![1, 2, 3]);
+        let mut clone = buf.clone();
+
+        assert_max_allocs(1, || clone.make_mut()[0] = 10);
+        assert_eq!(&*clone, &[10, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected at most 0 backing allocation(s), but 1 were made")]
+    fn test_assert_no_alloc_fails() {
+        assert_no_alloc(|| FigBuf::from_vec(vec![1, 2, 3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected at most 1 backing allocation(s), but 2 were made")]
+    fn test_guard_fails_on_drop() {
+        let _guard = AllocGuard::new(1);
+        let _a = FigBuf::from_vec(vec![1]);
+        let _b = FigBuf::from_vec(vec![2]);
+    }
+
+    #[test]
+    fn test_counts_are_per_thread() {
+        let guard = AllocGuard::new(0);
+        std::thread::spawn(|| FigBuf::from_vec(vec![0u8; 16]))
+            .join()
+            .unwrap();
+        assert_eq!(guard.count(), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use fig::bytes::Bytes;
+use fig::bytes::{Bytes, BytesMut};
 
 #[test]
 fn test_bytes_creation() {
@@ -215,3 +215,529 @@ fn test_bytes_large_buffer_sharing() {
         assert_eq!(slice[0], (i * 100 % 256) as u8);
     }
 }
+
+#[test]
+fn test_bytes_replace_all() {
+    let bytes = Bytes::from("GET /a/b/c HTTP/1.1");
+    let replaced = bytes.replace(b"/", b"%2F");
+
+    assert_eq!(&*replaced, b"GET %2Fa%2Fb%2Fc HTTP%2F1.1");
+    assert_eq!(&*bytes, b"GET /a/b/c HTTP/1.1");
+}
+
+#[test]
+fn test_bytes_replace_no_match_shares_buffer() {
+    let bytes = Bytes::from_vec(vec![1, 2, 3, 4, 5]);
+    let replaced = bytes.replace(&[9, 9], &[0]);
+
+    assert_eq!(replaced, bytes);
+    assert_eq!(replaced.as_ptr(), bytes.as_ptr());
+}
+
+#[test]
+fn test_bytes_replace_non_overlapping() {
+    let bytes = Bytes::from("aaaa");
+    assert_eq!(&*bytes.replace(b"aa", b"b"), b"bb");
+
+    let bytes = Bytes::from("aaa");
+    assert_eq!(&*bytes.replace(b"aa", b"b"), b"ba");
+}
+
+#[test]
+fn test_bytes_replace_shrink_and_grow() {
+    let bytes = Bytes::from("one two one");
+
+    assert_eq!(&*bytes.replace(b"one", b"1"), b"1 two 1");
+    assert_eq!(&*bytes.replace(b"two", b"three"), b"one three one");
+}
+
+#[test]
+fn test_bytes_replace_empty_needle() {
+    let bytes = Bytes::from("ab");
+    assert_eq!(&*bytes.replace(b"", b"-"), b"-a-b-");
+    assert_eq!(&*Bytes::new().replace(b"", b"-"), b"-");
+}
+
+#[test]
+fn test_bytes_replacen() {
+    let bytes = Bytes::from("a,b,c,d");
+
+    assert_eq!(&*bytes.replacen(b",", b";", 2), b"a;b;c,d");
+    assert_eq!(&*bytes.replacen(b",", b";", 10), b"a;b;c;d");
+
+    let untouched = bytes.replacen(b",", b";", 0);
+    assert_eq!(untouched.as_ptr(), bytes.as_ptr());
+}
+
+#[test]
+fn test_bytes_replace_on_slice() {
+    let bytes = Bytes::from("xx-yy-zz");
+    let middle = bytes.slice(3..8);
+
+    assert_eq!(&*middle.replace(b"-", b"+"), b"yy+zz");
+}
+
+#[test]
+fn test_bytes_rchunks() {
+    let bytes = Bytes::from_vec((0..10).collect());
+    let chunks: Vec<_> = bytes.rchunks(4).collect();
+
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(&*chunks[0], &[6, 7, 8, 9]);
+    assert_eq!(&*chunks[1], &[2, 3, 4, 5]);
+    assert_eq!(&*chunks[2], &[0, 1]);
+    assert_eq!(chunks[2].as_ptr(), bytes.as_ptr());
+}
+
+#[test]
+fn test_bytes_rchunks_trailer() {
+    // Trailer-based formats read a fixed-size footer from the end first.
+    let bytes = Bytes::from("payload-payload|FOOTER");
+    let footer = bytes.rchunks(6).next().unwrap();
+
+    assert_eq!(&*footer, b"FOOTER");
+}
+
+#[test]
+fn test_bytes_rsplit_tail_lines() {
+    let log = Bytes::from("first\nsecond\nthird\n");
+    let lines: Vec<_> = log.rsplit(|&b| b == b'\n').skip(1).take(2).collect();
+
+    assert_eq!(&*lines[0], b"third");
+    assert_eq!(&*lines[1], b"second");
+}
+
+#[test]
+fn test_bytes_rsplit_no_match() {
+    let bytes = Bytes::from("no separators");
+    let parts: Vec<_> = bytes.rsplit(|&b| b == b',').collect();
+
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0], bytes);
+}
+
+#[test]
+fn test_bytes_reclaim_handle() {
+    let bytes = Bytes::from_vec(vec![0; 16]);
+    let mut frame = bytes.clone();
+    let header = frame.split_to(4);
+    let handle = bytes.reclaim_handle().unwrap();
+
+    assert!(!handle.is_reclaimable());
+    assert_eq!(handle.ref_count(), 3);
+
+    drop(header);
+    drop(frame);
+
+    assert!(handle.is_reclaimable());
+    drop(handle);
+    assert_eq!(bytes.try_into_vec(), Ok(vec![0; 16]));
+}
+
+#[test]
+fn test_bytes_ensure_aligned() {
+    let bytes = Bytes::from_vec((0..=255).collect());
+    let mut record = bytes.slice(5..69);
+    record.ensure_aligned(8);
+
+    assert!(record.is_aligned(8));
+    assert_eq!(record.as_ptr() as usize % 8, 0);
+    assert_eq!(&*record, &bytes[5..69]);
+
+    let words: Vec<u64> = record
+        .chunks_exact(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(words.len(), 8);
+}
+
+#[test]
+fn test_bytes_ensure_aligned_direct_io() {
+    let mut block = Bytes::from_vec(vec![0xAB; 4096 * 2]).slice(3..4096 + 3);
+    block.ensure_aligned(4096);
+
+    assert!(block.is_aligned(4096));
+    assert_eq!(block.len(), 4096);
+    assert!(block.iter().all(|&b| b == 0xAB));
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_bytes_zero_copy_operations_do_not_allocate() {
+    use fig::testing::assert_no_alloc;
+
+    let bytes = Bytes::from_vec((0..64).collect());
+
+    assert_no_alloc(|| {
+        let mut frame = bytes.slice(8..56);
+        let header = frame.split_to(8);
+        let trailer = frame.split_off(32);
+        let chunks: Vec<_> = frame.rchunks(4).collect();
+        let unchanged = frame.replace(b"\xff\xff", b"");
+
+        assert_eq!(header.len(), 8);
+        assert_eq!(trailer.len(), 8);
+        assert_eq!(chunks.len(), 8);
+        assert_eq!(unchanged, frame);
+    });
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_bytes_replace_allocates_once() {
+    use fig::testing::count_allocs;
+
+    let bytes = Bytes::from("a-b-c-d");
+    let (replaced, allocs) = count_allocs(|| bytes.replace(b"-", b"--"));
+
+    assert_eq!(&*replaced, b"a--b--c--d");
+    assert_eq!(allocs, 1);
+}
+
+#[test]
+fn test_bytes_common_prefix_len() {
+    let bytes = Bytes::from("key:users:1042");
+
+    assert_eq!(bytes.common_prefix_len(b"key:users:2000"), 10);
+    assert_eq!(bytes.common_prefix_len(b"key:users:1042"), 14);
+    assert_eq!(bytes.common_prefix_len(b"other"), 0);
+    assert_eq!(bytes.common_prefix_len(b""), 0);
+}
+
+#[test]
+fn test_bytes_split_common_prefix() {
+    let a = Bytes::from_vec(b"0123456789abcdefXYZ".to_vec());
+    let b = Bytes::from_vec(b"0123456789abcdefxyz".to_vec());
+
+    let (prefix, a_rest, b_rest) = a.split_common_prefix(&b);
+
+    assert_eq!(&*prefix, b"0123456789abcdef");
+    assert_eq!(&*a_rest, b"XYZ");
+    assert_eq!(&*b_rest, b"xyz");
+    assert_eq!(prefix.as_ptr(), a.as_ptr());
+    assert_eq!(b_rest.as_ptr(), b[16..].as_ptr());
+}
+
+#[test]
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+fn test_bytes_io_buf() {
+    use tokio_uring::buf::IoBuf;
+
+    let bytes = Bytes::from("HTTP/1.1 200 OK\r\n\r\n");
+    let status = Bytes::slice(&bytes, 9..15);
+
+    assert_eq!(status.stable_ptr(), bytes[9..].as_ptr());
+    assert_eq!(status.bytes_init(), 6);
+    assert_eq!(status.bytes_total(), 6);
+
+    let line = IoBuf::slice(bytes, ..15);
+    assert_eq!(&line[..], b"HTTP/1.1 200 OK");
+}
+
+/// Starts an io_uring runtime, or returns `None` if the kernel or sandbox
+/// does not allow io_uring at all. Any other setup error fails the test.
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+fn uring_runtime() -> Option<tokio_uring::Runtime> {
+    const EPERM: i32 = 1;
+    const ENOSYS: i32 = 38;
+
+    match tokio_uring::Runtime::new(&tokio_uring::builder()) {
+        Ok(rt) => Some(rt),
+        Err(e) if matches!(e.raw_os_error(), Some(ENOSYS) | Some(EPERM)) => None,
+        Err(e) => panic!("failed to start io_uring runtime: {}", e),
+    }
+}
+
+#[test]
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+#[cfg_attr(miri, ignore)]
+fn test_bytes_io_uring_write() {
+    let Some(rt) = uring_runtime() else {
+        return;
+    };
+
+    let path = std::env::temp_dir().join(format!("fig-io-uring-write-{}", std::process::id()));
+    let payload = Bytes::from("written by io_uring");
+
+    let returned = rt
+        .block_on(async {
+            let file = tokio_uring::fs::File::create(&path).await?;
+            let (res, returned) = file.write_at(payload.clone(), 0).await;
+            res?;
+            file.close().await?;
+            Ok::<_, std::io::Error>(returned)
+        })
+        .unwrap();
+
+    assert_eq!(returned, payload);
+    assert_eq!(std::fs::read(&path).unwrap(), b"written by io_uring");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_bytes_mut_encode_frames() {
+    use std::io::Write;
+
+    let mut buf = BytesMut::with_capacity(64);
+    let start = buf.as_ptr();
+
+    let mut frames = Vec::new();
+    for i in 0..3 {
+        writeln!(buf, "frame {}", i).unwrap();
+        frames.push(buf.split().freeze());
+    }
+
+    assert_eq!(&*frames[0], b"frame 0\n");
+    assert_eq!(&*frames[1], b"frame 1\n");
+    assert_eq!(&*frames[2], b"frame 2\n");
+    assert_eq!(frames[0].as_ptr(), start);
+    assert_eq!(frames[1].as_ptr(), unsafe { start.add(8) });
+    assert_eq!(buf.capacity(), 64 - 24);
+}
+
+#[test]
+fn test_bytes_mut_split_off() {
+    let mut buf = BytesMut::from(&b"hello world"[..]);
+    let mut tail = buf.split_off(5);
+
+    tail.put_u8(b'!');
+    buf.extend_from_slice(b", there");
+
+    assert_eq!(buf, &b"hello, there"[..]);
+    assert_eq!(tail, &b" world!"[..]);
+}
+
+#[test]
+fn test_bytes_mut_reserve_grows() {
+    let mut buf = BytesMut::new();
+    for i in 0..100u8 {
+        buf.put_u8(i);
+    }
+
+    assert_eq!(buf.len(), 100);
+    assert!(buf.capacity() >= 100);
+    assert!(buf.iter().copied().eq(0..100u8));
+}
+
+#[test]
+fn test_bytes_mut_reserve_while_frozen_copies() {
+    let mut buf = BytesMut::with_capacity(8);
+    buf.put_slice(b"abcd");
+    let frozen = buf.split().freeze();
+
+    buf.put_slice(b"0123456789");
+
+    assert_eq!(&*frozen, b"abcd");
+    assert_eq!(&*buf, b"0123456789");
+}
+
+#[test]
+fn test_bytes_mut_deref_mut() {
+    let mut buf = BytesMut::from("hello");
+    buf.make_ascii_uppercase();
+    buf.truncate(4);
+
+    assert_eq!(&*buf.freeze(), b"HELL");
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_bytes_mut_freeze_no_alloc() {
+    use fig::testing::assert_no_alloc;
+
+    let mut buf = BytesMut::with_capacity(32);
+    let frame = assert_no_alloc(|| {
+        buf.put_slice(b"PING\r\n");
+        buf.split().freeze()
+    });
+
+    assert_eq!(&*frame, b"PING\r\n");
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_bytes_mut_new_no_alloc() {
+    use fig::testing::assert_no_alloc;
+
+    let buf = assert_no_alloc(|| {
+        let mut buf = BytesMut::new();
+        buf.put_slice(b"");
+        buf.clear();
+        buf
+    });
+
+    assert_eq!(buf.capacity(), 0);
+    assert_eq!(&*buf.freeze(), b"");
+}
+
+#[test]
+fn test_bytes_mut_write_while_frozen_read_across_threads() {
+    let mut buf = BytesMut::with_capacity(64);
+    buf.put_slice(b"first frame");
+    let frozen = buf.split().freeze();
+    let mut tail = buf.split_off(16);
+
+    let reader = std::thread::spawn(move || {
+        let copy = frozen.clone();
+        drop(frozen);
+        copy.iter().map(|&b| b as usize).sum::<usize>()
+    });
+    let writer = std::thread::spawn(move || {
+        tail.put_slice(b"written on another thread");
+        tail.freeze()
+    });
+    buf.put_slice(b"second");
+
+    let expected: usize = b"first frame".iter().map(|&b| b as usize).sum();
+    assert_eq!(reader.join().unwrap(), expected);
+    assert_eq!(&*writer.join().unwrap(), b"written on another thread");
+    assert_eq!(&*buf, b"second");
+}
+
+#[test]
+fn test_bytes_from_owner() {
+    struct Mapping(Box<[u8]>);
+
+    impl AsRef<[u8]> for Mapping {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    let data: Box<[u8]> = Box::from(&b"GET /index.html HTTP/1.1"[..]);
+    let ptr = data.as_ptr();
+    let bytes = Bytes::from_owner(Mapping(data));
+
+    let mut rest = bytes.clone();
+    let method = rest.split_to(4);
+    let path = rest.split_to(11);
+
+    assert_eq!(&*method, b"GET ");
+    assert_eq!(&*path, b"/index.html");
+    assert_eq!(method.as_ptr(), ptr);
+    assert_eq!(bytes.try_into_vec().unwrap_err().len(), 24);
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_bytes_from_owner_no_alloc() {
+    use fig::testing::assert_no_alloc;
+
+    let data = vec![7u8; 4096];
+    let bytes = assert_no_alloc(|| Bytes::from_owner(data).slice(1024..2048));
+
+    assert_eq!(bytes.len(), 1024);
+}
+
+#[test]
+fn test_bytes_mut_fmt_writer_status_line() {
+    use std::fmt::Write;
+
+    let mut buf = BytesMut::with_capacity(64);
+    let (code, reason) = (200, "OK");
+    write!(buf.as_fmt_writer(), "HTTP/1.1 {} {}\r\n", code, reason).unwrap();
+    let status = buf.split().freeze();
+
+    write!(buf.as_fmt_writer(), "Content-Length: {}\r\n", 42).unwrap();
+
+    assert_eq!(&*status, b"HTTP/1.1 200 OK\r\n");
+    assert_eq!(&*buf, b"Content-Length: 42\r\n");
+}
+
+#[test]
+fn test_bytes_try_into_mut_round_trip() {
+    let bytes = Bytes::from_vec(b"hello".to_vec());
+    let ptr = bytes.as_ptr();
+
+    let mut buf = bytes.try_into_mut().unwrap();
+    assert_eq!(&*buf, b"hello");
+    assert_eq!(buf.as_ptr(), ptr);
+
+    buf.make_ascii_uppercase();
+    let frozen = buf.freeze();
+    assert_eq!(&*frozen, b"HELLO");
+    assert_eq!(frozen.as_ptr(), ptr);
+
+    let buf = frozen.try_into_mut().unwrap();
+    assert_eq!(buf.as_ptr(), ptr);
+}
+
+#[test]
+fn test_bytes_try_into_mut_reuses_after_split() {
+    let mut buf = BytesMut::with_capacity(16);
+    buf.put_slice(b"header|body");
+    let start = buf.as_ptr();
+
+    let header = buf.split_to(7).freeze();
+    let body = buf.freeze();
+    drop(body);
+
+    let mut header = header.try_into_mut().unwrap();
+    header.clear();
+    header.reserve(16);
+    assert_eq!(header.as_ptr(), start);
+}
+
+#[test]
+fn test_bytes_try_into_mut_shared() {
+    let bytes = Bytes::from_vec(b"shared".to_vec());
+    let clone = bytes.clone();
+
+    let bytes = bytes.try_into_mut().unwrap_err();
+    assert_eq!(bytes.as_ptr(), clone.as_ptr());
+    assert_eq!(&*bytes, b"shared");
+
+    drop(clone);
+    assert!(bytes.try_into_mut().is_ok());
+}
+
+#[test]
+fn test_bytes_try_into_mut_reclaim_handle() {
+    let bytes = Bytes::from_vec(b"pooled".to_vec());
+    let handle = bytes.reclaim_handle().unwrap();
+
+    let bytes = bytes.try_into_mut().unwrap_err();
+
+    handle.wait();
+    assert!(bytes.try_into_mut().is_ok());
+}
+
+#[test]
+fn test_bytes_try_into_mut_owner() {
+    let bytes = Bytes::from_owner(b"owned".to_vec());
+    assert!(bytes.try_into_mut().is_err());
+}
+
+#[test]
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+#[cfg_attr(miri, ignore)]
+fn test_bytes_mut_io_uring_read() {
+    use tokio_uring::buf::IoBuf;
+
+    let Some(rt) = uring_runtime() else {
+        return;
+    };
+
+    let path = std::env::temp_dir().join(format!("fig-io-uring-read-{}", std::process::id()));
+    std::fs::write(&path, b"read by io_uring").unwrap();
+
+    let mut buf = BytesMut::with_capacity(64);
+    buf.put_slice(b"> ");
+    let start = buf.as_ptr();
+
+    let buf = rt
+        .block_on(async {
+            let file = tokio_uring::fs::File::open(&path).await?;
+            let (res, slice) = file.read_at(IoBuf::slice(buf, 2..), 0).await;
+            assert_eq!(res?, 16);
+            let buf = slice.into_inner();
+            file.close().await?;
+            Ok::<_, std::io::Error>(buf)
+        })
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(&*buf, b"> read by io_uring");
+    assert_eq!(buf.as_ptr(), start);
+    assert_eq!(&*buf.freeze(), b"> read by io_uring");
+}